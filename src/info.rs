@@ -0,0 +1,100 @@
+//! Parsing of UCI engine `info` lines into structured data.
+
+/// An engine's evaluation of a position, either a centipawn score or a forced mate in N.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Score {
+    Cp(i32),
+    Mate(i32),
+}
+
+/// The structured contents of a single `info` line emitted by a UCI engine.
+#[derive(Debug, Clone, Default)]
+pub struct EngineInfo {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time_ms: Option<u64>,
+    pub hashfull: Option<u32>,
+    pub pv: Vec<String>,
+}
+
+/// Parse a single `info ...` line into an [`EngineInfo`].
+///
+/// Returns `None` if `line` is not an `info` line. The `pv` keyword is assumed to be the
+/// last one on the line, and consumes every remaining token as a UCI move.
+pub fn parse_info(line: &str) -> Option<EngineInfo> {
+    let mut tokens = line.split_whitespace();
+    if tokens.next()? != "info" {
+        return None;
+    }
+
+    let mut info = EngineInfo::default();
+    while let Some(keyword) = tokens.next() {
+        match keyword {
+            "depth" => info.depth = tokens.next().and_then(|t| t.parse().ok()),
+            "seldepth" => info.seldepth = tokens.next().and_then(|t| t.parse().ok()),
+            "multipv" => info.multipv = tokens.next().and_then(|t| t.parse().ok()),
+            "nodes" => info.nodes = tokens.next().and_then(|t| t.parse().ok()),
+            "nps" => info.nps = tokens.next().and_then(|t| t.parse().ok()),
+            "time" => info.time_ms = tokens.next().and_then(|t| t.parse().ok()),
+            "hashfull" => info.hashfull = tokens.next().and_then(|t| t.parse().ok()),
+            "score" => {
+                info.score = match tokens.next() {
+                    Some("cp") => tokens.next().and_then(|t| t.parse().ok()).map(Score::Cp),
+                    Some("mate") => tokens.next().and_then(|t| t.parse().ok()).map(Score::Mate),
+                    _ => None,
+                };
+            }
+            "pv" => {
+                info.pv = tokens.map(String::from).collect();
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_info_lines_are_ignored() {
+        assert!(parse_info("bestmove e2e4").is_none());
+        assert!(parse_info("readyok").is_none());
+    }
+
+    #[test]
+    fn parses_depth_score_and_pv() {
+        let info = parse_info("info depth 12 seldepth 18 score cp 34 nodes 100000 nps 2000000 \
+                                time 50 hashfull 123 pv e2e4 e7e5 g1f3")
+            .unwrap();
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.seldepth, Some(18));
+        assert_eq!(info.score, Some(Score::Cp(34)));
+        assert_eq!(info.nodes, Some(100_000));
+        assert_eq!(info.nps, Some(2_000_000));
+        assert_eq!(info.time_ms, Some(50));
+        assert_eq!(info.hashfull, Some(123));
+        assert_eq!(info.pv, vec!["e2e4", "e7e5", "g1f3"]);
+    }
+
+    #[test]
+    fn parses_mate_score() {
+        let info = parse_info("info depth 5 score mate 3 pv a1a2").unwrap();
+        assert_eq!(info.score, Some(Score::Mate(3)));
+    }
+
+    #[test]
+    fn pv_consumes_rest_of_line_even_if_it_looks_like_a_keyword() {
+        // Tokens after `pv` that happen to be keywords ("depth", "score", "nodes") must not be
+        // reinterpreted.
+        let info = parse_info("info depth 1 pv depth score nodes").unwrap();
+        assert_eq!(info.pv, vec!["depth", "score", "nodes"]);
+    }
+}
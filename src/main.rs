@@ -16,6 +16,9 @@ use std::{
 
 use clap::Parser;
 
+mod info;
+use info::{EngineInfo, Score};
+
 const CONTROL_GREY: &str = "\u{001b}[38;5;243m";
 const CONTROL_GREEN: &str = "\u{001b}[32m";
 const CONTROL_RED: &str = "\u{001b}[31m";
@@ -69,6 +72,65 @@ pub struct Cli {
     /// Whether to grant a pass as soon as the engine's PV contains a best move.
     #[clap(long)]
     pub earlypass: bool,
+    /// Number of independent engine processes to spread the suite across.
+    #[clap(long, value_name = "N", default_value_t = 1)]
+    pub workers: usize,
+    /// Per-position idle timeout, in milliseconds: if the engine falls silent for this long,
+    /// it is sent `stop`, and the position is failed if it is still silent afterwards.
+    #[clap(long, value_name = "MS")]
+    pub timeout: Option<u64>,
+    /// White's remaining clock time, in milliseconds, to pass to `go`.
+    #[clap(long, value_name = "MS")]
+    pub wtime: Option<u64>,
+    /// Black's remaining clock time, in milliseconds, to pass to `go`.
+    #[clap(long, value_name = "MS")]
+    pub btime: Option<u64>,
+    /// White's increment per move, in milliseconds, to pass to `go`.
+    #[clap(long, value_name = "MS")]
+    pub winc: Option<u64>,
+    /// Black's increment per move, in milliseconds, to pass to `go`.
+    #[clap(long, value_name = "MS")]
+    pub binc: Option<u64>,
+    /// A fixed search depth to pass to `go`, instead of a time control.
+    #[clap(long, value_name = "PLIES")]
+    pub depth: Option<u32>,
+    /// A fixed node budget to pass to `go`, instead of a time control.
+    #[clap(long, value_name = "N")]
+    pub nodes: Option<u64>,
+    /// Limit the engine's playing strength to this Elo before running the suite, via
+    /// `UCI_LimitStrength`/`UCI_Elo`.
+    #[clap(long, value_name = "ELO")]
+    pub elo: Option<u32>,
+    /// Write a machine-readable report of the suite, alongside the human-coloured one.
+    /// Valid values are `json` and `csv`.
+    #[clap(long, value_name = "FORMAT")]
+    pub output: Option<OutputFormat>,
+    /// Only count a position as solved if the engine's final score is at least this many
+    /// centipawns (a reported mate for the side to move always counts as beyond any centipawn
+    /// threshold; a reported mate against it never does).
+    #[clap(long, value_name = "CP", allow_hyphen_values = true)]
+    pub min_score: Option<i32>,
+    /// Only count a position as solved if the engine reports a forced mate for the side to move.
+    #[clap(long)]
+    pub require_mate: bool,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(anyhow::anyhow!("Invalid output format: {}", s)),
+        }
+    }
 }
 
 const WIN_AT_CHESS: &str = include_str!("../epds/wac.epd");
@@ -78,39 +140,78 @@ const TABLEBASES: &str = include_str!("../epds/tbtest.epd");
 struct EpdPosition {
     fen: String,
     best_moves: Vec<String>,
+    avoid_moves: Vec<String>,
     id: String,
+    comment: Option<String>,
 }
 
-fn parse_epd(line: &str) -> Result<EpdPosition, anyhow::Error> {
-    static COUNTER: AtomicUsize = AtomicUsize::new(0);
-    let counter = COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-    let fen_string = line.split_whitespace().take(4).chain(Some("1 1")).collect::<Vec<_>>().join(" ");
-    let fen: Fen = fen_string.parse().with_context(|| format!("invalid fen: {fen_string}"))?;
-    let board: Chess =
-        fen.into_position(shakmaty::CastlingMode::Standard).with_context(|| format!("invalid fen: {fen_string}"))?;
-    let best_move_idx = line.find("bm").with_context(|| format!("no bestmove found in {line}"))?;
-    let best_moves = &line[best_move_idx + 3..];
-    let end_of_best_moves = best_moves.find(';').with_context(|| format!("no end of bestmove found in {line}"))?;
-    let best_moves = &best_moves[..end_of_best_moves].split(' ').collect::<Vec<_>>();
-    let best_moves = best_moves
-        .iter()
-        .map(|best_move| {
-            let san: San = best_move.parse().with_context(|| format!("invalid san: {best_move}"))?;
+/// Split the operations section of an EPD line (everything after the four FEN fields) into
+/// `(opcode, argument)` pairs, as delimited by `;`.
+fn parse_opcodes(operations: &str) -> impl Iterator<Item = (&str, &str)> {
+    operations.split(';').map(str::trim).filter(|op| !op.is_empty()).map(|op| {
+        let (opcode, arg) = op.split_once(char::is_whitespace).unwrap_or((op, ""));
+        (opcode, arg.trim())
+    })
+}
+
+/// Parse a whitespace-separated list of SAN moves (as used by the `bm`/`am` opcodes) into UCI
+/// move strings, relative to `board`.
+fn parse_san_moves(arg: &str, board: &Chess, fen_string: &str) -> Result<Vec<String>, anyhow::Error> {
+    arg.split_whitespace()
+        .map(|san_move| {
+            let san: San = san_move.parse().with_context(|| format!("invalid san: {san_move}"))?;
             let mv_string = san
-                .to_move(&board)
+                .to_move(board)
                 .with_context(|| format!("{san} is illegal in {fen_string}"))?
                 .to_uci(shakmaty::CastlingMode::Standard)
                 .to_string();
             Ok::<_, anyhow::Error>(mv_string)
         })
-        .collect::<Result<_, _>>()?;
-    let id_idx = line.find("id");
-    let id = if let Some(id_idx) = id_idx {
-        line[id_idx + 4..].split('"').next().with_context(|| format!("no id found in {line}"))?.to_string()
-    } else {
-        format!("position {counter}")
-    };
-    Ok(EpdPosition { fen: fen_string, best_moves, id })
+        .collect()
+}
+
+/// Strip the surrounding quotes from a quoted opcode argument such as `"mate in 2"`.
+fn unquote(arg: &str) -> &str {
+    arg.trim_matches('"')
+}
+
+fn parse_epd(line: &str) -> Result<EpdPosition, anyhow::Error> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let counter = COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    let mut rest = line;
+    let mut fen_fields = Vec::with_capacity(4);
+    for _ in 0..4 {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        fen_fields.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    let fen_string = fen_fields.into_iter().chain(Some("1 1")).collect::<Vec<_>>().join(" ");
+    let operations = rest.trim_start();
+    let fen: Fen = fen_string.parse().with_context(|| format!("invalid fen: {fen_string}"))?;
+    let board: Chess =
+        fen.into_position(shakmaty::CastlingMode::Standard).with_context(|| format!("invalid fen: {fen_string}"))?;
+
+    let mut best_moves = None;
+    let mut avoid_moves = Vec::new();
+    let mut id = None;
+    let mut comment = None;
+    for (opcode, arg) in parse_opcodes(operations) {
+        match opcode {
+            "bm" => best_moves = Some(parse_san_moves(arg, &board, &fen_string)?),
+            "am" => avoid_moves = parse_san_moves(arg, &board, &fen_string)?,
+            "id" => id = Some(unquote(arg).to_string()),
+            "c0" => comment = Some(unquote(arg).to_string()),
+            _ => {}
+        }
+    }
+    if best_moves.is_none() && avoid_moves.is_empty() {
+        anyhow::bail!("no bm or am opcode found in {line}");
+    }
+    let best_moves = best_moves.unwrap_or_default();
+    let id = id.unwrap_or_else(|| format!("position {counter}"));
+
+    Ok(EpdPosition { fen: fen_string, best_moves, avoid_moves, id, comment })
 }
 
 #[allow(clippy::too_many_lines)]
@@ -133,104 +234,475 @@ fn main() -> Result<(), anyhow::Error> {
     // Parse the EPD file into a vector of positions.
     let positions = epd_text.lines().map(parse_epd).collect::<Result<Vec<_>, _>>()?;
 
-    let (mut engine_stdin, mut engine_stdout) = boot_engine(&cli)?;
+    // cap the worker count at the suite size, since extra workers would just sit idle.
+    let workers = cli.workers.max(1).min(positions.len().max(1));
+    let maxfenlen = positions.iter().map(|pos| pos.fen.len()).max().unwrap();
+    let maxidlen = positions.iter().map(|pos| pos.id.len()).max().unwrap();
+    let n = positions.len();
+    let indexed_positions = positions.iter().enumerate().collect::<Vec<_>>();
+    let chunk_size = n.div_ceil(workers);
+
+    let start_time = std::time::Instant::now();
+    let mut results = if workers <= 1 {
+        let mut engine = init_engine(&cli)?;
+        run_positions(&cli, &mut engine, &indexed_positions, maxidlen, maxfenlen)?
+    } else {
+        std::thread::scope(|scope| {
+            // collect eagerly so every worker is spawned before any of them are joined
+            #[allow(clippy::needless_collect)]
+            let handles = indexed_positions
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(|| {
+                        let mut engine = init_engine(&cli)?;
+                        run_positions(&cli, &mut engine, chunk, maxidlen, maxfenlen)
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("engine worker thread panicked"))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|per_worker| per_worker.into_iter().flatten().collect::<Vec<_>>())
+        })?
+    };
+    // workers race each other, so results must be put back in suite order before reporting.
+    results.sort_by_key(|result| result.index);
 
-    // send the engine the UCI protocol commands to initialize it
-    write_line(cli.debug, &mut engine_stdin, "uci\n")?;
-    write_line(cli.debug, &mut engine_stdin, "isready\n")?;
+    let mut successes = 0;
+    let mut fail_messages = Vec::new();
+    let mut records = Vec::with_capacity(results.len());
+    for result in results {
+        println!("{}", result.output);
+        if result.passed {
+            successes += 1;
+        } else {
+            fail_messages.push(result.output);
+        }
+        records.push(result.record);
+    }
+    let elapsed = start_time.elapsed();
+    println!("{n} positions in {}.{:03}s", elapsed.as_secs(), elapsed.subsec_millis());
+    println!("{successes}/{n} passed");
+    if !fail_messages.is_empty() {
+        println!("{CONTROL_RED}FAILURES{CONTROL_RESET}:");
+        for fail_message in fail_messages {
+            println!("{fail_message}");
+        }
+    }
+
+    if let Some(format) = cli.output {
+        print_records(format, &records);
+    }
+
+    Ok(())
+}
+
+/// One engine's verdict for a single EPD position, tagged with the position's index in the
+/// original suite so that results can be put back in order after running in parallel.
+struct PositionResult {
+    index: usize,
+    output: String,
+    passed: bool,
+    record: PositionRecord,
+}
+
+/// A single position's result in a form suitable for machine-readable export via `--output`.
+struct PositionRecord {
+    id: String,
+    fen: String,
+    best_moves: Vec<String>,
+    avoid_moves: Vec<String>,
+    engine_move: Option<String>,
+    passed: bool,
+    think_ms: u128,
+    depth: Option<u32>,
+    score: Option<Score>,
+    nodes: Option<u64>,
+}
+
+/// Escape a string for embedding in a JSON string literal.
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Render a JSON array of UCI move strings.
+fn json_move_array(moves: &[String]) -> String {
+    let items = moves.iter().map(|m| format!("\"{}\"", json_escape(m))).collect::<Vec<_>>().join(",");
+    format!("[{items}]")
+}
+
+/// Render a `Score` as the small JSON object used by the `--output` report.
+fn json_score(score: Option<Score>) -> String {
+    match score {
+        Some(Score::Cp(cp)) => format!("{{\"cp\":{cp}}}"),
+        Some(Score::Mate(n)) => format!("{{\"mate\":{n}}}"),
+        None => "null".to_owned(),
+    }
+}
+
+/// Render a single [`PositionRecord`] as a JSON object.
+fn format_record_json(record: &PositionRecord) -> String {
+    let engine_move = record.engine_move.as_deref().map_or_else(|| "null".to_owned(), |m| format!("\"{}\"", json_escape(m)));
+    let depth = record.depth.map_or_else(|| "null".to_owned(), |d| d.to_string());
+    let nodes = record.nodes.map_or_else(|| "null".to_owned(), |n| n.to_string());
+    format!(
+        "{{\"id\":\"{}\",\"fen\":\"{}\",\"best_moves\":{},\"avoid_moves\":{},\"engine_move\":{},\
+         \"passed\":{},\"think_ms\":{},\"depth\":{},\"score\":{},\"nodes\":{}}}",
+        json_escape(&record.id),
+        json_escape(&record.fen),
+        json_move_array(&record.best_moves),
+        json_move_array(&record.avoid_moves),
+        engine_move,
+        record.passed,
+        record.think_ms,
+        depth,
+        json_score(record.score),
+        nodes,
+    )
+}
+
+/// Render every [`PositionRecord`] as a single JSON array.
+fn format_records_json(records: &[PositionRecord]) -> String {
+    let items = records.iter().map(format_record_json).collect::<Vec<_>>().join(",");
+    format!("[{items}]")
+}
+
+/// Escape and quote a field for embedding in a CSV row.
+fn csv_field(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+/// Render a `Score` as the short textual form used by the CSV report.
+fn csv_score(score: Option<Score>) -> String {
+    match score {
+        Some(Score::Cp(cp)) => format!("cp {cp}"),
+        Some(Score::Mate(n)) => format!("mate {n}"),
+        None => String::new(),
+    }
+}
+
+/// Render a single [`PositionRecord`] as one CSV row.
+fn format_record_csv(record: &PositionRecord) -> String {
+    [
+        csv_field(&record.id),
+        csv_field(&record.fen),
+        csv_field(&record.best_moves.join(" ")),
+        csv_field(&record.avoid_moves.join(" ")),
+        csv_field(record.engine_move.as_deref().unwrap_or("")),
+        record.passed.to_string(),
+        record.think_ms.to_string(),
+        record.depth.map_or_else(String::new, |d| d.to_string()),
+        csv_field(&csv_score(record.score)),
+        record.nodes.map_or_else(String::new, |n| n.to_string()),
+    ]
+    .join(",")
+}
+
+/// Print every [`PositionRecord`] to stdout in the requested machine-readable format.
+fn print_records(format: OutputFormat, records: &[PositionRecord]) {
+    match format {
+        OutputFormat::Json => println!("{}", format_records_json(records)),
+        OutputFormat::Csv => {
+            println!("id,fen,best_moves,avoid_moves,engine_move,passed,think_ms,depth,score,nodes");
+            for record in records {
+                println!("{}", format_record_csv(record));
+            }
+        }
+    }
+}
+
+/// Boot an engine and take it through the initial UCI handshake (`uci`/`isready`), then apply
+/// the CLI's `--option` settings.
+fn init_engine(cli: &Cli) -> Result<Engine, anyhow::Error> {
+    let mut engine = boot_engine(cli)?;
+
+    write_line(cli.debug, &mut engine.stdin, "uci\n")?;
+    write_line(cli.debug, &mut engine.stdin, "isready\n")?;
     // wait for the engine to respond
     loop {
-        let engine_response = read_line(cli.debug, &mut engine_stdout)?;
-        if engine_response.contains("readyok") {
+        let line = recv_line(cli.debug, &engine, None)?.expect("recv_line only returns None when timeout is set");
+        if line.text.contains("readyok") {
             break;
         }
     }
 
     // send the engine the UCI options to set
-    for option in cli.option {
+    for option in &cli.option {
         let (name, value) = option.split_once('=').with_context(|| format!("Invalid option: {option}"))?;
         let set_option_text = format!("setoption name {name} value {value}\n");
-        write_line(cli.debug, &mut engine_stdin, &set_option_text)?;
+        write_line(cli.debug, &mut engine.stdin, &set_option_text)?;
     }
 
-    // start the testing loop -
-    // for each position, send the engine the position and then check if the engine's best move matches any of
-    // the best moves in the EPD entry.
-    let go_cmd = cli.go.as_deref().unwrap_or("movetime 1000");
-    let mut successes = 0;
-    let maxfenlen = positions.iter().map(|pos| pos.fen.len()).max().unwrap();
-    let maxidlen = positions.iter().map(|pos| pos.id.len()).max().unwrap();
-    let n = positions.len();
-    let start_time = std::time::Instant::now();
-    let mut fail_messages = Vec::new();
-    for epd in positions {
-        // send `ucinewgame` to the engine to reset its internal state
-        write_line(cli.debug, &mut engine_stdin, "ucinewgame\n")?;
-        // send the position to the engine
-        write_line(cli.debug, &mut engine_stdin, &format!("position fen {}\n", epd.fen))?;
-        // send the `go` command to the engine to make it think about the position
-        write_line(cli.debug, &mut engine_stdin, &format!("go {go_cmd}\n"))?;
-        let think_start = std::time::Instant::now();
-        // wait for the engine to respond with `bestmove <move>`
-        let engine_response;
-        loop {
-            let line = read_line(cli.debug, &mut engine_stdout)?;
-            if cli.verbose {
-                println!("[{CONTROL_GREY}{id:midl$}{CONTROL_RESET}] {}", line.trim(), midl = maxidlen, id = epd.id,);
+    // cap the engine's playing strength, if requested
+    if let Some(elo) = cli.elo {
+        write_line(cli.debug, &mut engine.stdin, "setoption name UCI_LimitStrength value true\n")?;
+        write_line(cli.debug, &mut engine.stdin, &format!("setoption name UCI_Elo value {elo}\n"))?;
+    }
+
+    Ok(engine)
+}
+
+/// The outcome of waiting for an engine to reply to a `go` with `bestmove`.
+enum ThinkOutcome {
+    /// The engine (or an early-pass on its PV) settled on a move.
+    BestMove(String),
+    /// `--timeout` elapsed with no reply, even after a `stop` was sent.
+    TimedOut,
+}
+
+/// Send `epd` to `engine` (`ucinewgame`/`position fen`/`go`) and wait for its `bestmove`.
+///
+/// Any write failure (e.g. the engine died between positions and the pipe is now broken) is
+/// surfaced the same way a dead `wait_for_bestmove` is, so the caller can treat it as a crash
+/// and reboot rather than aborting the whole suite.
+fn drive_position(
+    cli: &Cli,
+    engine: &mut Engine,
+    epd: &EpdPosition,
+    maxidlen: usize,
+) -> Result<(ThinkOutcome, Option<EngineInfo>, std::time::Duration), anyhow::Error> {
+    // send `ucinewgame` to the engine to reset its internal state
+    write_line(cli.debug, &mut engine.stdin, "ucinewgame\n")?;
+    // send the position to the engine
+    write_line(cli.debug, &mut engine.stdin, &format!("position fen {}\n", epd.fen))?;
+    // send the `go` command to the engine to make it think about the position
+    let go_cmd = build_go_command(cli);
+    write_line(cli.debug, &mut engine.stdin, &format!("go {go_cmd}\n"))?;
+
+    wait_for_bestmove(cli, engine, epd, maxidlen)
+}
+
+/// Wait for `engine` to produce a `bestmove` for the position it was just sent, honouring
+/// `--earlypass` and the per-position `--timeout`. Returns the last `info` line seen alongside
+/// the outcome, so a timed-out position can still report how far the engine got.
+fn wait_for_bestmove(
+    cli: &Cli,
+    engine: &mut Engine,
+    epd: &EpdPosition,
+    maxidlen: usize,
+) -> Result<(ThinkOutcome, Option<EngineInfo>, std::time::Duration), anyhow::Error> {
+    let timeout = cli.timeout.map(std::time::Duration::from_millis);
+    let mut last_info = None;
+    let mut last_message_at = std::time::Instant::now();
+    let mut sent_stop = false;
+    loop {
+        let Some(line) = recv_line(cli.debug, engine, timeout)? else {
+            // nothing from the engine in a full `--timeout` window: give it one chance to stop
+            // cleanly, then give up on the position.
+            if sent_stop {
+                return Ok((ThinkOutcome::TimedOut, last_info, last_message_at.elapsed()));
             }
-            if line.contains("bestmove") {
-                engine_response = line;
-                break;
+            write_line(cli.debug, &mut engine.stdin, "stop\n")?;
+            sent_stop = true;
+            continue;
+        };
+        last_message_at = line.at;
+        if line.text.contains("bestmove") {
+            if cli.verbose {
+                println!(
+                    "[{CONTROL_GREY}{id:midl$}{CONTROL_RESET}] {}",
+                    line.text.trim(),
+                    midl = maxidlen,
+                    id = epd.id,
+                );
             }
-            let mut parts = line.split_whitespace();
-            if cli.earlypass && parts.any(|w| w == "pv") {
-                let choice = parts.next().expect("engine sent \"pv\" but no moves");
-
-                let passed = epd.best_moves.iter().any(|best_move| best_move == choice);
-
-                if passed {
-                    engine_response = format!("bestmove {choice}\n");
-                    // send "stop"
-                    write_line(cli.debug, &mut engine_stdin, "stop\n")?;
-                    // wait for the engine to respond with `bestmove <move>`
-                    loop {
-                        let line = read_line(cli.debug, &mut engine_stdout)?;
-                        if line.contains("bestmove") {
-                            break;
-                        }
-                    }
-                    break;
+            return Ok((ThinkOutcome::BestMove(line.text), last_info, last_message_at.elapsed()));
+        }
+        let Some(info) = info::parse_info(&line.text) else {
+            continue;
+        };
+        if cli.verbose {
+            println!(
+                "[{CONTROL_GREY}{id:midl$}{CONTROL_RESET}] {}",
+                format_engine_info(&info),
+                midl = maxidlen,
+                id = epd.id,
+            );
+        }
+        if info.pv.first().filter(|_| cli.earlypass).is_some_and(|choice| position_passed(epd, choice)) {
+            let bestmove = format!("bestmove {}\n", info.pv[0]);
+            write_line(cli.debug, &mut engine.stdin, "stop\n")?;
+            // wait for the engine to acknowledge, but don't let a slow ack cost a pass we
+            // already know is correct.
+            loop {
+                let Some(line) = recv_line(cli.debug, engine, timeout)? else {
+                    return Ok((ThinkOutcome::BestMove(bestmove), Some(info), last_message_at.elapsed()));
+                };
+                if line.text.contains("bestmove") {
+                    return Ok((ThinkOutcome::BestMove(bestmove), Some(info), line.at.elapsed()));
                 }
             }
         }
-        // parse the engine's best move
-        let engine_best_move = engine_response
-            .split_whitespace()
-            .nth(1)
-            .with_context(|| format!("Failed to parse engine response: {engine_response}"))?;
+        last_info = Some(info);
+    }
+}
+
+/// Run `positions` (each tagged with its index in the original suite) against a single engine,
+/// in order, and return the result of each position.
+///
+/// For each position, send the engine the position and then check if the engine's best move
+/// matches any of the best moves in the EPD entry. If the engine process has exited (or can no
+/// longer be written to), it is rebooted so the remaining positions can still be run; an engine
+/// that times out but is still running is kept.
+fn run_positions(
+    cli: &Cli,
+    engine: &mut Engine,
+    positions: &[(usize, &EpdPosition)],
+    maxidlen: usize,
+    maxfenlen: usize,
+) -> Result<Vec<PositionResult>, anyhow::Error> {
+    let mut results = Vec::with_capacity(positions.len());
+    for &(index, epd) in positions {
+        let think_start = std::time::Instant::now();
+        let think_outcome = drive_position(cli, engine, epd, maxidlen);
         let think_time = think_start.elapsed();
-        // check if the engine's best move matches any of the EPD's best moves
-        let passed = epd.best_moves.iter().any(|best_move| best_move == engine_best_move);
-        // print the result
-        let s = format_position_results(&epd, passed, think_time, engine_best_move, maxidlen, maxfenlen);
-        println!("{s}");
-        if passed {
-            successes += 1;
-        } else {
-            fail_messages.push(s);
+        let (output, passed, engine_move, last_info) = match think_outcome {
+            Ok((ThinkOutcome::BestMove(engine_response), last_info, ..)) => {
+                // parse the engine's best move
+                let engine_best_move = engine_response
+                    .split_whitespace()
+                    .nth(1)
+                    .with_context(|| format!("Failed to parse engine response: {engine_response}"))?;
+                // check if the engine's best move matches any of the EPD's best moves and avoids any of its
+                // avoid-moves, and (if `--min-score`/`--require-mate` were given) that it reports a strong
+                // enough evaluation to back that move up
+                let passed = position_passed(epd, engine_best_move) && score_passes(cli, last_info.as_ref());
+                (
+                    format_position_results(epd, passed, think_time, engine_best_move, maxidlen, maxfenlen),
+                    passed,
+                    Some(engine_best_move.to_string()),
+                    last_info,
+                )
+            }
+            Ok((ThinkOutcome::TimedOut, last_info, idle_for)) => {
+                // the engine may just be thinking slowly rather than dead; only reboot it if it has actually exited.
+                if !matches!(engine.child.try_wait(), Ok(None)) {
+                    reap_engine(engine);
+                    *engine = init_engine(cli)?;
+                }
+                let reason = format!("timeout, idle for {:.1}s", idle_for.as_secs_f64());
+                (
+                    format_timeout_result(epd, &reason, think_time, last_info.as_ref(), maxidlen, maxfenlen),
+                    false,
+                    None,
+                    last_info,
+                )
+            }
+            Err(_crashed) => {
+                reap_engine(engine);
+                *engine = init_engine(cli)?;
+                (format_timeout_result(epd, "engine crashed", think_time, None, maxidlen, maxfenlen), false, None, None)
+            }
+        };
+        let record = PositionRecord {
+            id: epd.id.clone(),
+            fen: epd.fen.clone(),
+            best_moves: epd.best_moves.clone(),
+            avoid_moves: epd.avoid_moves.clone(),
+            engine_move,
+            passed,
+            think_ms: think_time.as_millis(),
+            depth: last_info.as_ref().and_then(|info| info.depth),
+            score: last_info.as_ref().and_then(|info| info.score),
+            nodes: last_info.as_ref().and_then(|info| info.nodes),
+        };
+        results.push(PositionResult { index, output, passed, record });
+    }
+    Ok(results)
+}
+
+/// Whether the final evaluation the engine reported clears the `--min-score`/`--require-mate`
+/// bar, if either was set. With neither set, every score passes.
+fn score_passes(cli: &Cli, info: Option<&EngineInfo>) -> bool {
+    if cli.min_score.is_none() && !cli.require_mate {
+        return true;
+    }
+    let Some(score) = info.and_then(|info| info.score) else {
+        return false;
+    };
+    if cli.require_mate && !matches!(score, Score::Mate(n) if n > 0) {
+        return false;
+    }
+    if let Some(min_score) = cli.min_score {
+        let meets_min = match score {
+            Score::Mate(n) => n > 0,
+            Score::Cp(cp) => cp >= min_score,
+        };
+        if !meets_min {
+            return false;
         }
     }
-    let elapsed = start_time.elapsed();
-    println!("{n} positions in {}.{:03}s", elapsed.as_secs(), elapsed.subsec_millis());
-    println!("{successes}/{n} passed");
-    if !fail_messages.is_empty() {
-        println!("{CONTROL_RED}FAILURES{CONTROL_RESET}:");
-        for fail_message in fail_messages {
-            println!("{fail_message}");
+    true
+}
+
+/// Build the `go` command to send for each position.
+///
+/// `--go` is a raw escape hatch and, if given, overrides everything else. Otherwise, any of
+/// `--wtime`/`--btime`/`--winc`/`--binc`/`--depth`/`--nodes` that were given are assembled into
+/// `go`'s arguments, except that `--winc`/`--binc` are only passed when `--wtime` or `--btime`
+/// was also given and are otherwise ignored; if only one side's clock was given, it is mirrored onto the other so every
+/// position gets a clock regardless of which side the FEN has to move. This is a deliberate
+/// simplification: since the mirrored value ends up identical on both sides, reading the FEN's
+/// side to move would not change the result, so `build_go_command` does not need `epd` at all.
+/// With none of these flags set, it falls back to the previous default of `movetime 1000`.
+fn build_go_command(cli: &Cli) -> String {
+    if let Some(go) = cli.go.as_deref() {
+        return go.to_string();
+    }
+
+    let mut parts = Vec::new();
+    if cli.wtime.is_some() || cli.btime.is_some() {
+        let given_time = cli.wtime.or(cli.btime);
+        let wtime = cli.wtime.or(given_time).unwrap_or(0);
+        let btime = cli.btime.or(given_time).unwrap_or(0);
+        parts.push(format!("wtime {wtime} btime {btime}"));
+        if let Some(winc) = cli.winc {
+            parts.push(format!("winc {winc}"));
         }
+        if let Some(binc) = cli.binc {
+            parts.push(format!("binc {binc}"));
+        }
+    }
+    if let Some(depth) = cli.depth {
+        parts.push(format!("depth {depth}"));
+    }
+    if let Some(nodes) = cli.nodes {
+        parts.push(format!("nodes {nodes}"));
     }
 
-    Ok(())
+    if parts.is_empty() {
+        "movetime 1000".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Pretty-print the fields of an `info` line that are most useful to a human watching a live run.
+fn format_engine_info(info: &EngineInfo) -> String {
+    let mut parts = Vec::new();
+    if let Some(depth) = info.depth {
+        parts.push(format!("depth {depth}"));
+    }
+    if let Some(score) = info.score {
+        parts.push(match score {
+            Score::Cp(cp) => format!("score cp {cp}"),
+            Score::Mate(mate) => format!("score mate {mate}"),
+        });
+    }
+    if !info.pv.is_empty() {
+        parts.push(format!("pv {}", info.pv.join(" ")));
+    }
+    parts.join(" ")
 }
 
 fn format_position_results(
@@ -251,6 +723,15 @@ fn format_position_results(
             san.to_string()
         })
         .collect::<Vec<_>>();
+    let avoid_move_sans = epd
+        .avoid_moves
+        .iter()
+        .map(|mv| {
+            let uci = Uci::from_str(mv).unwrap();
+            let san = SanPlus::from_move(position.clone(), &uci.to_move(&position).unwrap());
+            san.to_string()
+        })
+        .collect::<Vec<_>>();
     let engine_best_move_san =
         SanPlus::from_move(position.clone(), &Uci::from_str(engine_best_move).unwrap().to_move(&position).unwrap())
             .to_string();
@@ -272,8 +753,14 @@ fn format_position_results(
         }
     };
     let move_strings = best_move_sans.iter().map(move_fmt).collect::<Vec<_>>().join(", ");
+    let avoid_fmt = if avoid_move_sans.is_empty() {
+        String::new()
+    } else {
+        format!(" {CONTROL_GREY}avoid [{}]{CONTROL_RESET}", avoid_move_sans.join(", "))
+    };
+    let comment = epd.comment.as_deref().map_or(String::new(), |c| format!(" {CONTROL_GREY}// {c}{CONTROL_RESET}"));
     format!(
-        "[{CONTROL_GREY}{id:midl$}{CONTROL_RESET}] {fen:mfl$} {colour}{}{CONTROL_RESET} [{move_strings}]{failinfo}",
+        "[{CONTROL_GREY}{id:midl$}{CONTROL_RESET}] {fen:mfl$} {colour}{}{CONTROL_RESET} [{move_strings}]{avoid_fmt}{failinfo}{comment}",
         if passed { "PASS" } else { "FAIL" },
         midl = maxidlen,
         mfl = maxfenlen,
@@ -282,32 +769,116 @@ fn format_position_results(
     )
 }
 
-fn boot_engine(cli: &Cli) -> Result<(std::process::ChildStdin, BufReader<std::process::ChildStdout>), anyhow::Error> {
-    let mut engine_process = std::process::Command::new(&cli.engine)
+/// Format the result line for a position that never produced a `bestmove`, reporting the last
+/// depth/score the engine reached (if any) before it went quiet.
+fn format_timeout_result(
+    epd: &EpdPosition,
+    reason: &str,
+    think_time: std::time::Duration,
+    last_info: Option<&EngineInfo>,
+    maxidlen: usize,
+    maxfenlen: usize,
+) -> String {
+    let progress = last_info.map_or(String::new(), |info| format!(" last seen: {}", format_engine_info(info)));
+    format!(
+        "[{CONTROL_GREY}{id:midl$}{CONTROL_RESET}] {fen:mfl$} {CONTROL_RED}FAIL{CONTROL_RESET} \
+         {CONTROL_GREY}{:.1}s{CONTROL_RESET} {CONTROL_RED}{reason}{CONTROL_RESET}{progress}",
+        think_time.as_secs_f64(),
+        midl = maxidlen,
+        mfl = maxfenlen,
+        id = epd.id,
+        fen = epd.fen,
+    )
+}
+
+/// Whether `engine_move` satisfies `epd`: it must be one of the `best_moves` (if any were
+/// given) and must not be one of the `avoid_moves`.
+fn position_passed(epd: &EpdPosition, engine_move: &str) -> bool {
+    (epd.best_moves.is_empty() || epd.best_moves.iter().any(|m| m == engine_move))
+        && !epd.avoid_moves.iter().any(|m| m == engine_move)
+}
+
+/// A line read from an engine's stdout, stamped with when it arrived so a watchdog can measure
+/// idle time since the engine's last message rather than only total elapsed time.
+struct EngineLine {
+    text: String,
+    at: std::time::Instant,
+}
+
+/// A running UCI engine process: its stdin for sending commands, and the receiving end of a
+/// background thread that streams timestamped lines from its stdout, so callers can wait for
+/// output with a timeout instead of blocking forever.
+struct Engine {
+    child: std::process::Child,
+    stdin: std::process::ChildStdin,
+    lines: std::sync::mpsc::Receiver<EngineLine>,
+}
+
+/// Kill and reap a still-running engine process before discarding it, so rebooting a crashed or
+/// timed-out engine over a long suite run doesn't leave a zombie behind for every recovery.
+fn reap_engine(engine: &mut Engine) {
+    let _ = engine.child.kill();
+    let _ = engine.child.wait();
+}
+
+fn boot_engine(cli: &Cli) -> Result<Engine, anyhow::Error> {
+    let mut child = std::process::Command::new(&cli.engine)
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .spawn()
         .expect("Failed to spawn engine process");
-    let engine_stdin = engine_process
-        .stdin
+    let stdin =
+        child.stdin.take().with_context(|| format!("Failed to take stdin of engine process {}", cli.engine.display()))?;
+    let stdout = child
+        .stdout
         .take()
-        .with_context(|| format!("Failed to take stdin of engine process {}", cli.engine.display()))?;
-    let engine_stdout = BufReader::new(
-        engine_process
-            .stdout
-            .take()
-            .with_context(|| format!("Failed to take stdout of engine process {}", cli.engine.display()))?,
-    );
-    Ok((engine_stdin, engine_stdout))
-}
-
-fn read_line(debug: bool, reader: &mut BufReader<std::process::ChildStdout>) -> Result<String, anyhow::Error> {
-    let mut line = String::new();
-    reader.read_line(&mut line).with_context(|| "Failed to read from engine process")?;
+        .with_context(|| format!("Failed to take stdout of engine process {}", cli.engine.display()))?;
+
+    // stream the engine's stdout into a channel so `recv_line` can wait on it with a timeout.
+    let (tx, lines) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(stdout);
+        let mut text = String::new();
+        loop {
+            text.clear();
+            match reader.read_line(&mut text) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {
+                    let line = EngineLine { text: std::mem::take(&mut text), at: std::time::Instant::now() };
+                    if tx.send(line).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(Engine { child, stdin, lines })
+}
+
+/// Wait for the next line from `engine`, blocking forever if `timeout` is `None`.
+///
+/// Returns `Ok(None)` if `timeout` elapses with no line arriving, and an error if the engine's
+/// stdout closed (i.e. the process exited) before producing one.
+fn recv_line(
+    debug: bool,
+    engine: &Engine,
+    timeout: Option<std::time::Duration>,
+) -> Result<Option<EngineLine>, anyhow::Error> {
+    let line = match timeout {
+        Some(timeout) => match engine.lines.recv_timeout(timeout) {
+            Ok(line) => line,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => return Ok(None),
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                anyhow::bail!("engine process exited unexpectedly")
+            }
+        },
+        None => engine.lines.recv().map_err(|_| anyhow::anyhow!("engine process exited unexpectedly"))?,
+    };
     if debug {
-        eprintln!("[?] ENGINE -> TOOL: {}", line.trim());
+        eprintln!("[?] ENGINE -> TOOL: {}", line.text.trim());
     }
-    Ok(line)
+    Ok(Some(line))
 }
 
 fn write_line(debug: bool, writer: &mut std::process::ChildStdin, line: &str) -> Result<(), anyhow::Error> {
@@ -317,3 +888,106 @@ fn write_line(debug: bool, writer: &mut std::process::ChildStdin, line: &str) ->
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_cli() -> Cli {
+        Cli {
+            engine: std::path::PathBuf::new(),
+            inbuilt: None,
+            epdpath: None,
+            option: Vec::new(),
+            verbose: false,
+            debug: false,
+            go: None,
+            earlypass: false,
+            workers: 1,
+            timeout: None,
+            wtime: None,
+            btime: None,
+            winc: None,
+            binc: None,
+            depth: None,
+            nodes: None,
+            elo: None,
+            output: None,
+            min_score: None,
+            require_mate: false,
+        }
+    }
+
+    #[test]
+    fn parse_opcodes_splits_on_semicolons_and_trims() {
+        let ops = r#"bm e4; id "position 1"; c0 "a comment";"#;
+        let parsed = parse_opcodes(ops).collect::<Vec<_>>();
+        assert_eq!(parsed, vec![("bm", "e4"), ("id", "\"position 1\""), ("c0", "\"a comment\"")]);
+    }
+
+    #[test]
+    fn parse_epd_reads_bm_and_am() {
+        let epd =
+            parse_epd(r"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - bm Bb5; am Bc4;").unwrap();
+        assert_eq!(epd.best_moves, vec!["f1b5"]);
+        assert_eq!(epd.avoid_moves, vec!["f1c4"]);
+    }
+
+    #[test]
+    fn parse_epd_requires_bm_or_am() {
+        assert!(parse_epd("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq -").is_err());
+    }
+
+    #[test]
+    fn score_passes_with_no_thresholds_always_passes() {
+        let cli = base_cli();
+        assert!(score_passes(&cli, None));
+    }
+
+    #[test]
+    fn score_passes_enforces_min_score() {
+        let mut cli = base_cli();
+        cli.min_score = Some(100);
+        let low = EngineInfo { score: Some(Score::Cp(50)), ..Default::default() };
+        let high = EngineInfo { score: Some(Score::Cp(150)), ..Default::default() };
+        assert!(!score_passes(&cli, Some(&low)));
+        assert!(score_passes(&cli, Some(&high)));
+    }
+
+    #[test]
+    fn score_passes_enforces_require_mate() {
+        let mut cli = base_cli();
+        cli.require_mate = true;
+        let cp_score = EngineInfo { score: Some(Score::Cp(10_000)), ..Default::default() };
+        let mate_score = EngineInfo { score: Some(Score::Mate(3)), ..Default::default() };
+        assert!(!score_passes(&cli, Some(&cp_score)));
+        assert!(score_passes(&cli, Some(&mate_score)));
+    }
+
+    #[test]
+    fn build_go_command_honours_explicit_go_override() {
+        let mut cli = base_cli();
+        cli.go = Some("infinite".to_string());
+        assert_eq!(build_go_command(&cli), "infinite");
+    }
+
+    #[test]
+    fn build_go_command_mirrors_wtime_onto_btime() {
+        let mut cli = base_cli();
+        cli.wtime = Some(5000);
+        assert_eq!(build_go_command(&cli), "wtime 5000 btime 5000");
+    }
+
+    #[test]
+    fn build_go_command_mirrors_btime_onto_wtime() {
+        let mut cli = base_cli();
+        cli.btime = Some(3000);
+        assert_eq!(build_go_command(&cli), "wtime 3000 btime 3000");
+    }
+
+    #[test]
+    fn build_go_command_falls_back_to_movetime() {
+        let cli = base_cli();
+        assert_eq!(build_go_command(&cli), "movetime 1000");
+    }
+}